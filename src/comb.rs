@@ -0,0 +1,242 @@
+//! High-level combinators built on top of the primitive `Parser`.
+//!
+//! Assembling a real grammar out of atomic `tag`/`re`/`ws` calls means
+//! hand-rolling backtracking loops everywhere. These combinators do that
+//! bookkeeping once, using `Parser::state`/`Parser::reset` to roll a failed
+//! attempt back: each one takes an `FnMut(&mut Parser) -> PRes<T>` and rolls
+//! back the parser's position \(and line tracking\) on failure. When every
+//! alternative fails, `alt` returns the error of the one that got furthest
+//! into the input, so the final `ParsErr` reports the deepest failure point
+//! rather than the outermost one; `opt`, `many` and `sep_by` discard the
+//! errors of their children.
+
+use { Parser, Pos, PRes, ParsErr } ;
+
+
+/// Runs `f`, resetting the parser and returning `None` if it fails.
+///
+/// # Examples
+///
+/// ```rust
+/// use parsimple::Parser ;
+/// use parsimple::comb::opt ;
+///
+/// let mut parser = Parser::new("blah end", 0) ;
+/// let res = opt(& mut parser, |p| p.tag("blah")) ;
+/// assert_eq! { res, Some(()) }
+/// assert_eq! { parser.rest(), " end" }
+///
+/// let res = opt(& mut parser, |p| p.tag("nope")) ;
+/// assert_eq! { res, None }
+/// assert_eq! { parser.rest(), " end" }
+/// ```
+pub fn opt<'s, T, F>(parser: & mut Parser<'s>, mut f: F) -> Option<T>
+where F: FnMut(& mut Parser<'s>) -> PRes<T> {
+  let state = parser.state() ;
+  match f(parser) {
+    Ok(res) => Some(res),
+    Err(_) => {
+      parser.reset(& state) ;
+      None
+    },
+  }
+}
+
+
+/// Tries each alternative in `alts` in order, resetting the parser between
+/// attempts.
+///
+/// If all alternatives fail, keeps the error from whichever one advanced
+/// furthest into the input before failing \(rather than, say, the last
+/// one tried\), since that is typically the most informative one.
+///
+/// # Examples
+///
+/// ```rust
+/// use parsimple::Parser ;
+/// use parsimple::comb::alt ;
+///
+/// let mut parser = Parser::new("blah", 0) ;
+/// let res = alt(
+///   & mut parser,
+///   & mut [
+///     & mut |p: & mut Parser| p.tag("nope"),
+///     & mut |p: & mut Parser| p.tag("blah"),
+///   ],
+/// ) ;
+/// assert! { res.is_ok() }
+/// ```
+pub fn alt<'s, T>(
+  parser: & mut Parser<'s>,
+  alts: & mut [& mut FnMut(& mut Parser<'s>) -> PRes<T>],
+) -> PRes<T> {
+  let start = parser.state() ;
+  let mut furthest: Option<(Pos, ParsErr)> = None ;
+
+  for f in alts.iter_mut() {
+    parser.reset(& start) ;
+    match f(parser) {
+      Ok(res) => return Ok(res),
+      Err(e) => {
+        let reached = parser.pos() ;
+        let keep = match & furthest {
+          None => true,
+          Some((best, _)) => reached > * best,
+        } ;
+        if keep {
+          furthest = Some((reached, e))
+        }
+      },
+    }
+  }
+
+  parser.reset(& start) ;
+  Err(
+    furthest.map(|(_, e)| e).unwrap_or_else(
+      || parser.error_here("no alternative matched")
+    )
+  )
+}
+
+
+/// Repeats `f` until it fails, collecting the successes.
+///
+/// Always succeeds, possibly with an empty `Vec` if `f` never matches. If
+/// `f` succeeds without consuming any input, stops right there instead of
+/// looping forever, the way nom's `many0` guards against this exact
+/// footgun \(the zero-width match itself is not included in the result\).
+///
+/// # Examples
+///
+/// ```rust
+/// use parsimple::{ Parser, Regex } ;
+/// use parsimple::comb::many ;
+///
+/// let mut parser = Parser::new("aaab", 0) ;
+/// let res = many(& mut parser, |p| p.tag("a")) ;
+/// assert_eq! { res.len(), 3 }
+/// assert_eq! { parser.rest(), "b" }
+///
+/// // A zero-width-matching parser does not loop forever.
+/// let mut parser = Parser::new("abc", 0) ;
+/// let space_re = Regex::new(r"^[ ]*").unwrap() ;
+/// let res = many(& mut parser, |p| p.re(& space_re)) ;
+/// assert_eq! { res.len(), 0 }
+/// assert_eq! { parser.rest(), "abc" }
+/// ```
+pub fn many<'s, T, F>(parser: & mut Parser<'s>, mut f: F) -> Vec<T>
+where F: FnMut(& mut Parser<'s>) -> PRes<T> {
+  let mut res = vec![] ;
+  loop {
+    let before = parser.pos() ;
+    let state = parser.state() ;
+    match f(parser) {
+      Ok(item) => {
+        if parser.pos() == before {
+          parser.reset(& state) ;
+          break
+        }
+        res.push(item)
+      },
+      Err(_) => {
+        parser.reset(& state) ;
+        break
+      },
+    }
+  }
+  res
+}
+
+
+/// Like `many`, but fails if `f` does not match at least once.
+///
+/// # Examples
+///
+/// ```rust
+/// use parsimple::Parser ;
+/// use parsimple::comb::many1 ;
+///
+/// let mut parser = Parser::new("aaab", 0) ;
+/// let res = many1(& mut parser, |p| p.tag("a")).unwrap() ;
+/// assert_eq! { res.len(), 3 }
+///
+/// let mut parser = Parser::new("b", 0) ;
+/// assert! { many1(& mut parser, |p| p.tag("a")).is_err() }
+/// ```
+pub fn many1<'s, T, F>(parser: & mut Parser<'s>, mut f: F) -> PRes<Vec<T>>
+where F: FnMut(& mut Parser<'s>) -> PRes<T> {
+  let state = parser.state() ;
+  let first = match f(parser) {
+    Ok(first) => first,
+    Err(e) => {
+      parser.reset(& state) ;
+      return Err(e)
+    },
+  } ;
+  let mut res = vec![first] ;
+  res.extend( many(parser, f) ) ;
+  Ok(res)
+}
+
+
+/// Parses a list of `item`s separated by `sep`, e.g. `a, b, c`.
+///
+/// Always succeeds, possibly with an empty `Vec` if `item` does not match
+/// at the start of the list.
+///
+/// # Examples
+///
+/// ```rust
+/// use parsimple::Parser ;
+/// use parsimple::comb::sep_by ;
+///
+/// let mut parser = Parser::new("a, a, a b", 0) ;
+/// let res = sep_by(
+///   & mut parser,
+///   |p| p.tag("a"),
+///   |p| { p.ws() ; p.tag(",")? ; p.ws() ; Ok(()) },
+/// ) ;
+/// assert_eq! { res.len(), 3 }
+/// assert_eq! { parser.rest(), " b" }
+/// ```
+pub fn sep_by<'s, T, S, FItem, FSep>(
+  parser: & mut Parser<'s>, mut item: FItem, mut sep: FSep
+) -> Vec<T>
+where
+FItem: FnMut(& mut Parser<'s>) -> PRes<T>,
+FSep: FnMut(& mut Parser<'s>) -> PRes<S> {
+  let mut res = vec![] ;
+
+  let state = parser.state() ;
+  match item(parser) {
+    Ok(first) => res.push(first),
+    Err(_) => {
+      parser.reset(& state) ;
+      return res
+    },
+  }
+
+  loop {
+    let before = parser.pos() ;
+    let state = parser.state() ;
+    if sep(parser).is_err() {
+      parser.reset(& state) ;
+      break
+    }
+    match item(parser) {
+      // Guards against a `sep`/`item` pair that together match without
+      // consuming input, the same footgun `many` guards against.
+      Ok(_) if parser.pos() == before => {
+        parser.reset(& state) ;
+        break
+      },
+      Ok(next) => res.push(next),
+      Err(_) => {
+        parser.reset(& state) ;
+        break
+      },
+    }
+  }
+
+  res
+}
@@ -4,12 +4,53 @@ extern crate regex ;
 
 pub use regex::Regex ;
 
+pub mod comb ;
+
 // #[cfg(test)]
 mod test ;
 
 
 
+/// A pluggable regex engine.
+///
+/// `try_re`/`re` \(and friends\) are generic over this trait rather than
+/// hard-wired to `regex::Regex`, which by design rejects look-around and
+/// backreferences. Implement it for e.g. a fancy-regex-style engine to use
+/// such patterns, without this crate taking a hard dependency on it.
+///
+/// `match_at` must be anchored at the start of `hay`, as if the pattern
+/// were prefixed with `^`: a match starting anywhere else does not count.
+pub trait Matcher {
+  /// Byte length of a match anchored at the start of `hay`, if any.
+  fn match_at(& self, hay: & str) -> Option<usize> ;
+  /// Human-readable description of this matcher, used in error messages.
+  fn describe(& self) -> String {
+    "<matcher>".to_string()
+  }
+}
+
+impl Matcher for Regex {
+  fn match_at(& self, hay: & str) -> Option<usize> {
+    self.find(hay).and_then(
+      |found_it| if found_it.start() == 0 {
+        Some(found_it.end())
+      } else {
+        None
+      }
+    )
+  }
+  fn describe(& self) -> String {
+    self.as_str().to_string()
+  }
+}
+
+
+
 /// A position in the parser.
+///
+/// Ordered by the underlying offset, so combinators can tell which of two
+/// positions is furthest along in the input.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Pos {
   /// Actual position
   pos: usize,
@@ -19,11 +60,67 @@ pub struct Pos {
 /// Parse result.
 pub type PRes<T> = Result<T, ParsErr> ;
 
+/// The kind of a parse error, for machine-readable dispatch.
+///
+/// `tag`/`re` populate this automatically; callers doing their own
+/// semantic validation (after the primitive parse succeeded) can use
+/// `Custom` and attach labeled spans with `ParsErr::with_span` instead.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParsErrKind {
+  /// A specific tag was expected but not found.
+  ExpectedTag(String),
+  /// A regex did not match.
+  NoRegexMatch(String),
+  /// Something unexpected was found, with no more specific kind.
+  Unexpected,
+  /// Anything else, e.g. a semantic-validation error raised by a caller.
+  Custom,
+}
+
+/// A labeled secondary span attached to a `ParsErr`: a rendered range in
+/// the source together with its own message, e.g. a "note" pointing back
+/// at an earlier token. Built with `Parser::labeled_span`.
+#[derive(Debug, Clone)]
+pub struct Span {
+  /// Line number of the span.
+  line: usize,
+  /// Column (1-based) of the start of the span.
+  col: usize,
+  /// Span line: before the span.
+  prf: String,
+  /// Span line: the span itself.
+  tkn: String,
+  /// Span line: after the span.
+  suf: String,
+  /// Label for this span.
+  label: String,
+}
+impl Span {
+  /// Line number of this span.
+  pub fn line(& self) -> usize {
+    self.line
+  }
+  /// Column (1-based, in characters) of the start of this span.
+  pub fn col(& self) -> usize {
+    self.col
+  }
+  /// Prefix, token, and suffix of this span's line.
+  pub fn err(& self) -> (& str, & str, & str) {
+    (& self.prf, & self.tkn, & self.suf)
+  }
+  /// Label for this span.
+  pub fn label(& self) -> & str {
+    & self.label
+  }
+}
+
 /// Parse error.
 #[derive(Debug)]
 pub struct ParsErr {
   /// Line/col position.
   pos: (usize, usize),
+  /// Kind of this error.
+  kind: ParsErrKind,
   /// Error messages.
   msg: Vec<String>,
   /// Error line: before error token.
@@ -32,6 +129,8 @@ pub struct ParsErr {
   tkn: String,
   /// Error line: after error token.
   suf: String,
+  /// Secondary labeled spans, e.g. "note" annotations.
+  spans: Vec<Span>,
 }
 impl ParsErr {
   /// Pushes a new error message.
@@ -43,6 +142,10 @@ impl ParsErr {
   pub fn pos(& self) -> (usize, usize) {
     self.pos
   }
+  /// Kind of this error.
+  pub fn kind(& self) -> & ParsErrKind {
+    & self.kind
+  }
   /// Error messages.
   pub fn msg(& self) -> & [String] {
     & self.msg
@@ -51,6 +154,16 @@ impl ParsErr {
   pub fn err(& self) -> (& str, & str, & str) {
     (& self.prf, & self.tkn, & self.suf)
   }
+  /// Secondary labeled spans attached to this error.
+  pub fn spans(& self) -> & [Span] {
+    & self.spans
+  }
+  /// Attaches a labeled secondary span to this error. Returns `self` for
+  /// chaining.
+  pub fn with_span(mut self, span: Span) -> Self {
+    self.spans.push(span) ;
+    self
+  }
 
   /// Applies some treatment to each line of the error.
   pub fn default_lines<F: FnMut(& str)>(
@@ -63,10 +176,20 @@ impl ParsErr {
     treatment(& format!("| {}{}{}", self.prf, self.tkn, self.suf)) ;
     treatment(
       &  format!(
-        "| {0: ^1$}{2}", "", self.prf.len(),
-        & format!("{0:^>1$}", "", self.tkn.len())
+        "| {0: ^1$}{2}", "", self.prf.chars().count(),
+        & format!("{0:^>1$}", "", self.tkn.chars().count())
       )
-    )
+    ) ;
+    for span in & self.spans {
+      treatment(& format!("note [{}, {}]: {}", span.line, span.col, span.label)) ;
+      treatment(& format!("| {}{}{}", span.prf, span.tkn, span.suf)) ;
+      treatment(
+        &  format!(
+          "| {0: ^1$}{2}", "", span.prf.chars().count(),
+          & format!("{0:^>1$}", "", span.tkn.chars().count())
+        )
+      )
+    }
   }
 
   /// Multi-line default representation.
@@ -90,6 +213,34 @@ impl ParsErr {
 
 
 
+/// A parser checkpoint, for backtracking.
+///
+/// Captures everything needed to roll a [`Parser`](struct.Parser.html) back
+/// to an earlier point: the position, and the incrementally-tracked line
+/// info used by `error`/`error_here`. Obtained with `Parser::state` and
+/// restored with `Parser::reset`.
+#[derive(Clone, Copy, Debug)]
+pub struct ParserState {
+  /// Position in the text.
+  position: usize,
+  /// Byte offset of the start of the current line.
+  line_start: usize,
+  /// Current line number.
+  line_number: usize,
+}
+
+
+/// Byte offset of the end of the character starting at byte offset `at` in
+/// `s`, i.e. the next `char_indices` boundary. Returns `at` unchanged if
+/// there is no character there.
+fn char_end(s: & str, at: usize) -> usize {
+  s[at ..]
+    .chars().next()
+    .map(|c| at + c.len_utf8())
+    .unwrap_or(at)
+}
+
+
 /// Parser.
 pub struct Parser<'s> {
   /// Text being parsed.
@@ -98,19 +249,55 @@ pub struct Parser<'s> {
   pos: usize,
   /// Line offset, for errors.
   line_offset: usize,
+  /// Byte offset of the start of the current line.
+  current_line_start: usize,
+  /// Current line number.
+  current_line_number: usize,
 }
 
 impl<'s> Parser<'s> {
   /// Constructor.
   pub fn new(text: & 's str, line_offset: usize) -> Self {
-    Parser { text, pos: 0, line_offset }
+    Parser {
+      text, pos: 0, line_offset,
+      current_line_start: 0, current_line_number: line_offset + 1,
+    }
   }
   /// Changes the text being parsed.
   ///
   /// Resets the position.
   pub fn set(& mut self, text: & 's str, line_offset: usize) {
     self.text = text ;
-    self.line_offset = line_offset
+    self.pos = 0 ;
+    self.line_offset = line_offset ;
+    self.current_line_start = 0 ;
+    self.current_line_number = line_offset + 1
+  }
+
+  /// Saves a full checkpoint of the parser's state.
+  pub fn state(& self) -> ParserState {
+    ParserState {
+      position: self.pos,
+      line_start: self.current_line_start,
+      line_number: self.current_line_number,
+    }
+  }
+  /// Restores the parser to a checkpoint obtained with `state`.
+  pub fn reset(& mut self, state: & ParserState) {
+    self.pos = state.position ;
+    self.current_line_start = state.line_start ;
+    self.current_line_number = state.line_number
+  }
+
+  /// Updates the incremental line tracking for a slice of `text` that was
+  /// just consumed, starting at byte offset `start`.
+  fn track_lines(& mut self, consumed: & str, start: usize) {
+    for (offset, chr) in consumed.char_indices() {
+      if chr == '\n' {
+        self.current_line_number += 1 ;
+        self.current_line_start = start + offset + 1
+      }
+    }
   }
 
   /// True if at EOF.
@@ -135,12 +322,75 @@ impl<'s> Parser<'s> {
 
 
 
+  /// Checks whether `tag` matches at the current position, without
+  /// consuming any input.
+  ///
+  /// Like `try_tag`, but never advances `pos`. Lets a grammar disambiguate
+  /// between alternatives before committing to one.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// use parsimple::Parser ;
+  /// let mut parser = Parser::new("blah end", 0) ;
+  /// assert! { parser.peek_tag("blah") }
+  /// assert_eq! { parser.rest(), "blah end" }
+  ///
+  /// // A tag that exactly fills the remaining input still matches.
+  /// let parser = Parser::new("ab", 0) ;
+  /// assert! { parser.peek_tag("ab") }
+  /// ```
+  pub fn peek_tag(& self, tag: & str) -> bool {
+    if self.rest().len() < tag.len() {
+      false
+    } else {
+      & self.text[self.pos .. self.pos + tag.len()] == tag
+    }
+  }
+
+  /// Checks whether `re` matches at the current position, without
+  /// consuming any input.
+  ///
+  /// Like `try_re_str`, but never advances `pos`.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// use parsimple::{ Parser, Regex } ;
+  /// let mut parser = Parser::new("blah end", 0) ;
+  /// let alpha_re = Regex::new(r"[a-zA-Z]+").unwrap() ;
+  /// assert_eq! { parser.peek_re(& alpha_re), Some("blah") }
+  /// assert_eq! { parser.rest(), "blah end" }
+  /// ```
+  pub fn peek_re<M: Matcher>(& self, m: & M) -> Option<& 's str> {
+    m.match_at(& self.text[self.pos ..]).map(
+      |end_offset| & self.text[self.pos .. self.pos + end_offset]
+    )
+  }
+
+  /// The next character, without consuming it.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// use parsimple::Parser ;
+  /// let parser = Parser::new("blah", 0) ;
+  /// assert_eq! { parser.peek_char(), Some('b') }
+  /// ```
+  pub fn peek_char(& self) -> Option<char> {
+    self.text[self.pos ..].chars().next()
+  }
+
+
+
   /// Consumes all whitespaces after the current position.
   pub fn ws(& mut self) {
+    let start = self.pos ;
     let rest = & self.text[ self.pos .. ] ;
     let trimmed = rest.trim_left() ;
     let diff = rest.len() - trimmed.len() ;
-    self.pos += diff
+    self.pos += diff ;
+    self.track_lines(& self.text[start .. self.pos], start)
   }
 
 
@@ -160,13 +410,32 @@ impl<'s> Parser<'s> {
   /// assert_eq! { parser.rest(), "  end" }
   /// ```
   pub fn try_tag(& mut self, tag: & str) -> bool {
-    if self.chars_left() < tag.len() {
-      false
+    self.try_tag_str(tag).is_some()
+  }
+  /// Tries to parse a tag, returning the matched slice without allocating.
+  ///
+  /// Like `try_tag`, but on success yields a borrowed slice of `text` tied
+  /// to the parser's lifetime `'s` instead of just a boolean.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// use parsimple::Parser ;
+  /// let mut parser = Parser::new("   blah  end", 0) ;
+  /// parser.ws() ;
+  /// assert_eq! { parser.try_tag_str("blah"), Some("blah") }
+  /// assert_eq! { parser.try_tag_str("end"), None }
+  /// ```
+  pub fn try_tag_str(& mut self, tag: & str) -> Option<& 's str> {
+    if self.rest().len() < tag.len() {
+      None
     } else if & self.text[self.pos .. self.pos + tag.len()] == tag {
+      let start = self.pos ;
       self.pos += tag.len() ;
-      true
+      self.track_lines(& self.text[start .. self.pos], start) ;
+      Some(& self.text[start .. self.pos])
     } else {
-      false
+      None
     }
   }
   /// Parses a tag or fails.
@@ -195,7 +464,8 @@ impl<'s> Parser<'s> {
   pub fn tag(& mut self, tag: & str) -> PRes<()> {
     if ! self.try_tag(tag) {
       Err(
-        self.error_here(
+        self.error_here_kind(
+          ParsErrKind::ExpectedTag( tag.to_string() ),
           format!("expected tag `{}`", tag)
         )
       )
@@ -203,19 +473,35 @@ impl<'s> Parser<'s> {
       Ok(())
     }
   }
+  /// Parses a tag or fails, returning the matched slice without allocating.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// use parsimple::Parser ;
+  /// let mut parser = Parser::new("   blah  end", 0) ;
+  /// parser.ws() ;
+  /// assert_eq! { parser.tag_str("blah").unwrap(), "blah" }
+  /// ```
+  pub fn tag_str(& mut self, tag: & str) -> PRes<& 's str> {
+    if let Some(res) = self.try_tag_str(tag) {
+      Ok(res)
+    } else {
+      Err(
+        self.error_here_kind(
+          ParsErrKind::ExpectedTag( tag.to_string() ),
+          format!("expected tag `{}`", tag)
+        )
+      )
+    }
+  }
 
 
   /// Tries to parse a regex.
   ///
-  /// A regex's result is only considered relevant if the match starts at the
-  /// current position. Hence, for efficiency reasons, all regexes should start
-  /// with `^` indicating the start of the string.
-  ///
-  /// Otherwise, `Regex` will try to match over the rest of the text in its
-  /// entirety, but the result will be ignored by the parser (unless it starts
-  /// at the current position).
-  ///
-  /// See the second call to `try_re` in the example below.
+  /// Generic over `Matcher`, so any regex engine can be plugged in \(not
+  /// just `regex::Regex`\); see `Matcher` for the anchoring contract that
+  /// replaces the old "start with `^`" documentation caveat.
   ///
   /// # Examples
   ///
@@ -234,17 +520,34 @@ impl<'s> Parser<'s> {
   /// let res = parser.try_re(& alpha_re) ;
   /// assert_eq! { res, None }
   /// ```
-  pub fn try_re(& mut self, re: & Regex) -> Option<String> {
-    if let Some(found_it) = re.find(& self.text[self.pos ..]) {
-      println!("start: {}, end: {}", found_it.start(), found_it.end()) ;
-      if found_it.start() == 0 {
-        let end = self.pos + found_it.end() ;
-        println!("pos: {}, end: {}", self.pos, end) ;
-        let start = ::std::mem::replace(& mut self.pos, end) ;
-        Some( self.text[start .. self.pos].into() )
-      } else {
-        None
-      }
+  pub fn try_re<M: Matcher>(& mut self, m: & M) -> Option<String> {
+    self.try_re_str(m).map(|s| s.into())
+  }
+  /// Tries to parse a regex, returning the match without allocating.
+  ///
+  /// Like `try_re`, but on success yields a borrowed slice of `text` tied to
+  /// the parser's lifetime `'s` instead of an owned `String`. Useful for
+  /// parsers that tokenize large inputs, where per-token allocations would
+  /// otherwise dominate runtime.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// use parsimple::{ Parser, Regex } ;
+  ///
+  /// let mut parser = Parser::new("   blah  end", 0) ;
+  /// parser.ws() ;
+  /// let alpha_re = Regex::new(r"[a-zA-Z]+").unwrap() ;
+  /// assert_eq! { parser.try_re_str(& alpha_re), Some("blah") }
+  /// assert_eq! { parser.try_re_str(& alpha_re), None }
+  /// ```
+  pub fn try_re_str<M: Matcher>(& mut self, m: & M) -> Option<& 's str> {
+    if let Some(end_offset) = m.match_at(& self.text[self.pos ..]) {
+      let start = self.pos ;
+      let end = self.pos + end_offset ;
+      self.pos = end ;
+      self.track_lines(& self.text[start .. end], start) ;
+      Some(& self.text[start .. end])
     } else {
       None
     }
@@ -276,13 +579,38 @@ impl<'s> Parser<'s> {
   ///   "
   /// }
   /// ```
-  pub fn re(& mut self, re: & Regex) -> PRes<String> {
-    if let Some(res) = self.try_re(re) {
+  pub fn re<M: Matcher>(& mut self, m: & M) -> PRes<String> {
+    if let Some(res) = self.try_re(m) {
       return Ok(res)
     } else {
       Err(
-        self.error_here(
-          format!("no match for regex `{}`", re.as_str())
+        self.error_here_kind(
+          ParsErrKind::NoRegexMatch( m.describe() ),
+          format!("no match for regex `{}`", m.describe())
+        )
+      )
+    }
+  }
+  /// Parses a regex or fails, returning the match without allocating.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// use parsimple::{ Parser, Regex } ;
+  ///
+  /// let mut parser = Parser::new("   blah  end", 0) ;
+  /// parser.ws() ;
+  /// let alpha_re = Regex::new(r"[a-zA-Z]+").unwrap() ;
+  /// assert_eq! { parser.re_str(& alpha_re).unwrap(), "blah" }
+  /// ```
+  pub fn re_str<M: Matcher>(& mut self, m: & M) -> PRes<& 's str> {
+    if let Some(res) = self.try_re_str(m) {
+      Ok(res)
+    } else {
+      Err(
+        self.error_here_kind(
+          ParsErrKind::NoRegexMatch( m.describe() ),
+          format!("no match for regex `{}`", m.describe())
         )
       )
     }
@@ -296,35 +624,160 @@ impl<'s> Parser<'s> {
     let pos = self.pos() ;
     self.error(pos, msg)
   }
+  /// Generates a parse error at the current position, with an explicit,
+  /// machine-readable kind.
+  pub fn error_here_kind<S: Into<String>>(
+    & self, kind: ParsErrKind, msg: S
+  ) -> ParsErr {
+    let pos = self.pos() ;
+    self.error_kind(pos, kind, msg)
+  }
 
   /// Generates a parse error at the given position.
   pub fn error<S: Into<String>>(
     & self, pos: Pos, msg: S
   ) -> ParsErr {
-    let mut pos = pos.pos ;
+    self.error_kind(pos, ParsErrKind::Unexpected, msg)
+  }
+
+  /// Generates a parse error at the given position, with an explicit,
+  /// machine-readable kind.
+  ///
+  /// When `pos` is the parser's current position \(the common case, e.g. from
+  /// `error_here`\), the error's line/column is computed from the
+  /// incrementally-tracked `current_line_start`/`current_line_number`,
+  /// looking only at the current line rather than re-walking the text
+  /// before it. For
+  /// any other position \(typically one saved earlier and no longer
+  /// current\), falls back to scanning `self.text` line by line.
+  ///
+  /// The column and error token are char-boundary aware: the column counts
+  /// characters \(not bytes\) from the start of the line, and the token is
+  /// sliced up to the next `char_indices` boundary, so this never panics on
+  /// multibyte input.
+  pub fn error_kind<S: Into<String>>(
+    & self, pos: Pos, kind: ParsErrKind, msg: S
+  ) -> ParsErr {
     let msg = msg.into() ;
+    if pos.pos == self.pos {
+      let line_end = self.text[self.current_line_start ..]
+        .find('\n')
+        .map(|offset| self.current_line_start + offset)
+        .unwrap_or_else(|| self.text.len()) ;
+      let line = & self.text[self.current_line_start .. line_end] ;
+      let col_byte = pos.pos - self.current_line_start ;
+      let col = line[0 .. col_byte].chars().count() ;
+      let (prf, tkn, suf) = if self.is_eof() {
+        ( line.to_string(), "<eof>".to_string(), "".to_string() )
+      } else if col_byte < line.len() {
+        let tkn_end = char_end(line, col_byte) ;
+        (
+          line[0..col_byte].to_string(),
+          line[col_byte..tkn_end].to_string(),
+          line[tkn_end..line.len()].to_string(),
+        )
+      } else {
+        ( line.to_string(), "\\n".to_string(), "".to_string() )
+      } ;
+      ParsErr {
+        pos: (self.current_line_number, col + 1), kind,
+        msg: vec![msg], prf, tkn, suf, spans: vec![],
+      }
+    } else {
+      self.error_slow(pos, kind, msg)
+    }
+  }
+
+  /// Fallback for `error_kind`: scans `self.text` line by line to find
+  /// `pos`.
+  ///
+  /// Used when `pos` no longer matches the parser's current position, since
+  /// line tracking is only maintained incrementally for the current one.
+  fn error_slow(& self, pos: Pos, kind: ParsErrKind, msg: String) -> ParsErr {
+    let mut pos_byte = pos.pos ;
     let mut line_count = self.line_offset ;
     let (mut prf, mut tkn, mut suf) = (
       "".to_string(), "<eof>".to_string(), "".to_string()
     ) ;
+    let mut col = 0 ;
     for line in self.text.lines() {
       line_count += 1 ;
-      if pos < line.len() {
-        prf = line[0..pos].to_string() ;
-        tkn = line[pos..(pos + 1)].to_string() ;
-        suf = line[(pos + 1)..line.len()].to_string() ;
+      if pos_byte < line.len() {
+        col = line[0 .. pos_byte].chars().count() ;
+        let tkn_end = char_end(line, pos_byte) ;
+        prf = line[0..pos_byte].to_string() ;
+        tkn = line[pos_byte..tkn_end].to_string() ;
+        suf = line[tkn_end..line.len()].to_string() ;
         break
-      } else if pos == line.len() {
+      } else if pos_byte == line.len() {
+        col = line.chars().count() ;
         prf = line.into() ;
         tkn = "\\n".into() ;
         suf = "".into() ;
         break
       } else {
-        pos -= line.len() + 1
+        pos_byte -= line.len() + 1
       }
     }
     ParsErr {
-      pos: (line_count, pos + 1), msg: vec![msg], prf, tkn, suf
+      pos: (line_count, col + 1), kind, msg: vec![msg], prf, tkn, suf, spans: vec![],
+    }
+  }
+
+  /// Finds the line containing byte offset `pos`: its 1-based line number,
+  /// its text (without the trailing newline), and its starting byte offset.
+  fn line_at(& self, pos: usize) -> (usize, String, usize) {
+    let mut rem = pos ;
+    let mut line_count = self.line_offset ;
+    let mut start = 0 ;
+    for line in self.text.lines() {
+      line_count += 1 ;
+      if rem <= line.len() {
+        return (line_count, line.to_string(), start)
+      }
+      rem -= line.len() + 1 ;
+      start += line.len() + 1
+    }
+    (line_count, "".to_string(), start)
+  }
+
+  /// Builds a labeled span over `[start, end)`, for attaching to an error
+  /// as a secondary annotation with `ParsErr::with_span` \(e.g. a "note"
+  /// pointing back at an earlier token\).
+  ///
+  /// Assumes `start` and `end` lie on the same line. Like `error_kind`, the
+  /// reported column counts characters \(not bytes\) from the start of the
+  /// line.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// use parsimple::{ Parser, ParsErrKind } ;
+  /// let mut parser = Parser::new("h\u{e9}llo foo bar", 0) ;
+  /// parser.re_str(& parsimple::Regex::new(r"^\S+").unwrap()).unwrap() ;
+  /// parser.ws() ;
+  /// let first = parser.pos() ;
+  /// parser.tag("foo").unwrap() ;
+  /// let first_end = parser.pos() ;
+  /// let span = parser.labeled_span(first, first_end, "here") ;
+  /// let err = parser.error_here_kind(ParsErrKind::Custom, "bla").with_span(span) ;
+  /// assert_eq! { err.spans().len(), 1 }
+  /// assert_eq! { err.spans()[0].col(), 7 }
+  /// assert_eq! { err.spans()[0].err().1, "foo" }
+  /// ```
+  pub fn labeled_span<S: Into<String>>(
+    & self, start: Pos, end: Pos, label: S
+  ) -> Span {
+    let (line, text, line_start) = self.line_at(start.pos) ;
+    let col_byte = start.pos - line_start ;
+    let col = text[0..col_byte].chars().count() ;
+    let tkn_end = (end.pos - line_start).min(text.len()) ;
+    Span {
+      line, col: col + 1,
+      prf: text[0..col_byte].to_string(),
+      tkn: text[col_byte..tkn_end].to_string(),
+      suf: text[tkn_end..text.len()].to_string(),
+      label: label.into(),
     }
   }
 }
\ No newline at end of file